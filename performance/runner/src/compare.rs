@@ -0,0 +1,340 @@
+use crate::types::{HyperfineResult, MetricModel, Provenance, Sample};
+
+// Default one-sided significance threshold: a result is only flagged as
+// Regressed if the t-test p-value is below this.
+pub const DEFAULT_SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+// Minimum relative slowdown (new_mean / base_mean - 1) required on top of
+// statistical significance, so a tiny-but-"significant" blip doesn't fail CI.
+pub const DEFAULT_MIN_RELATIVE_SLOWDOWN: f64 = 0.05;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Verdict {
+    Improved,
+    NoChange,
+    Regressed,
+}
+
+// The statistic backing a Verdict, so callers can print it alongside the verdict.
+#[derive(Clone, Copy, Debug)]
+pub struct ComparisonStats {
+    pub t_statistic: Option<f64>,
+    pub degrees_of_freedom: Option<f64>,
+    pub relative_change: f64,
+}
+
+// Two results taken on different hosts/CPUs aren't a fair comparison: warn
+// the caller rather than silently reporting a verdict either way.
+pub fn is_comparable(a: &Provenance, b: &Provenance) -> bool {
+    a.hostname == b.hostname
+        && a.os == b.os
+        && a.arch == b.arch
+        && a.cpu_model == b.cpu_model
+        && a.cpu_cores == b.cpu_cores
+}
+
+// A lighter-weight check than `compare`: flags a memory regression even when
+// timing came back NoChange, since peak RSS isn't part of the t-test in `compare`.
+pub fn memory_regressed(sample: &Sample, baseline: &MetricModel, min_relative_growth: f64) -> bool {
+    match (&sample.measurement.resources, &baseline.measurement.resources) {
+        (Some(new), Some(base)) if base.max_rss_kb > 0 => {
+            let relative_growth =
+                (new.max_rss_kb as f64 - base.max_rss_kb as f64) / base.max_rss_kb as f64;
+            relative_growth > min_relative_growth
+        }
+        _ => false,
+    }
+}
+
+// Compares a freshly taken Sample against the matching MetricModel from a Baseline.
+// Callers are expected to have already joined the two on `Metric`.
+pub fn compare(
+    sample: &Sample,
+    baseline: &MetricModel,
+    significance_level: f64,
+    min_relative_slowdown: f64,
+) -> (Verdict, ComparisonStats) {
+    let new = &sample.measurement;
+    let base = &baseline.measurement;
+
+    let relative_change = (new.mean - base.mean) / base.mean;
+
+    let n_new = new.times.len();
+    let n_base = base.times.len();
+
+    // with too few runs to trust a t-test, fall back to a simple sigma threshold.
+    if n_new < 2 || n_base < 2 {
+        let verdict = if base.stddev == 0.0 {
+            simple_verdict(new.mean, base.mean, relative_change, min_relative_slowdown)
+        } else if new.mean > base.mean + 2.0 * base.stddev && relative_change > min_relative_slowdown {
+            Verdict::Regressed
+        } else if new.mean < base.mean - 2.0 * base.stddev {
+            Verdict::Improved
+        } else {
+            Verdict::NoChange
+        };
+
+        return (
+            verdict,
+            ComparisonStats {
+                t_statistic: None,
+                degrees_of_freedom: None,
+                relative_change,
+            },
+        );
+    }
+
+    let (t, df) = welch_t_test(new, base);
+
+    // zero variance on both sides: the t-test is degenerate, fall back to comparing means directly.
+    if new.stddev == 0.0 && base.stddev == 0.0 {
+        let verdict = simple_verdict(new.mean, base.mean, relative_change, min_relative_slowdown);
+        return (
+            verdict,
+            ComparisonStats {
+                t_statistic: Some(t),
+                degrees_of_freedom: Some(df),
+                relative_change,
+            },
+        );
+    }
+
+    let p_value = one_sided_p_value(t, df);
+
+    let verdict = if p_value < significance_level
+        && new.mean > base.mean
+        && relative_change > min_relative_slowdown
+    {
+        Verdict::Regressed
+    } else if new.mean < base.mean && one_sided_p_value(-t, df) < significance_level {
+        Verdict::Improved
+    } else {
+        Verdict::NoChange
+    };
+
+    (
+        verdict,
+        ComparisonStats {
+            t_statistic: Some(t),
+            degrees_of_freedom: Some(df),
+            relative_change,
+        },
+    )
+}
+
+fn simple_verdict(new_mean: f64, base_mean: f64, relative_change: f64, min_relative_slowdown: f64) -> Verdict {
+    if new_mean > base_mean && relative_change > min_relative_slowdown {
+        Verdict::Regressed
+    } else if new_mean < base_mean {
+        Verdict::Improved
+    } else {
+        Verdict::NoChange
+    }
+}
+
+// Welch's two-sample t-test, returning (t, degrees of freedom).
+fn welch_t_test(new: &HyperfineResult, base: &HyperfineResult) -> (f64, f64) {
+    let n_new = new.times.len() as f64;
+    let n_base = base.times.len() as f64;
+
+    let var_new = new.stddev.powi(2);
+    let var_base = base.stddev.powi(2);
+
+    let se_new = var_new / n_new;
+    let se_base = var_base / n_base;
+
+    let t = (new.mean - base.mean) / (se_new + se_base).sqrt();
+
+    let df = (se_new + se_base).powi(2)
+        / (se_new.powi(2) / (n_new - 1.0) + se_base.powi(2) / (n_base - 1.0));
+
+    (t, df)
+}
+
+// One-sided p-value for a t-statistic with the given degrees of freedom, via a
+// normal approximation (valid for the run counts hyperfine produces, which are
+// rarely small enough for the approximation to matter).
+fn one_sided_p_value(t: f64, df: f64) -> f64 {
+    1.0 - standard_normal_cdf(t * (1.0 - 1.0 / (4.0 * df)))
+}
+
+fn standard_normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2))
+}
+
+// Abramowitz and Stegun 7.1.26 approximation of the error function.
+fn erf(x: f64) -> f64 {
+    let sign = if x < 0.0 { -1.0 } else { 1.0 };
+    let x = x.abs();
+
+    let a1 = 0.254829592;
+    let a2 = -0.284496736;
+    let a3 = 1.421413741;
+    let a4 = -1.453152027;
+    let a5 = 1.061405429;
+    let p = 0.3275911;
+
+    let t = 1.0 / (1.0 + p * x);
+    let y = 1.0 - (((((a5 * t + a4) * t) + a3) * t + a2) * t + a1) * t * (-x * x).exp();
+
+    sign * y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Metric, ResourceUsage};
+    use chrono::Utc;
+
+    fn hyperfine_result(mean: f64, stddev: f64, n: usize) -> HyperfineResult {
+        HyperfineResult {
+            command: "dbt parse --no-version-check".to_owned(),
+            mean,
+            stddev,
+            median: mean,
+            min: mean,
+            max: mean,
+            times: vec![mean; n],
+            resources: None,
+        }
+    }
+
+    fn hyperfine_result_with_rss(mean: f64, stddev: f64, n: usize, max_rss_kb: u64) -> HyperfineResult {
+        let mut result = hyperfine_result(mean, stddev, n);
+        result.resources = Some(ResourceUsage {
+            max_rss_kb,
+            user_cpu_s: mean,
+            system_cpu_s: 0.0,
+        });
+        result
+    }
+
+    fn provenance() -> Provenance {
+        Provenance {
+            dbt_commit_sha: Some("deadbeef".to_owned()),
+            hostname: "ci-runner".to_owned(),
+            os: "linux".to_owned(),
+            arch: "x86_64".to_owned(),
+            cpu_model: "generic".to_owned(),
+            cpu_cores: 4,
+            end_ts: Utc::now(),
+        }
+    }
+
+    fn sample(measurement: HyperfineResult) -> Sample {
+        Sample {
+            metric: Metric {
+                name: "parse".to_owned(),
+                project_name: "2000_models".to_owned(),
+            },
+            ts: Utc::now(),
+            measurement,
+            provenance: provenance(),
+        }
+    }
+
+    fn model(measurement: HyperfineResult) -> MetricModel {
+        MetricModel {
+            metric: Metric {
+                name: "parse".to_owned(),
+                project_name: "2000_models".to_owned(),
+            },
+            ts: Utc::now(),
+            measurement,
+        }
+    }
+
+    #[test]
+    fn welch_t_test_is_positive_and_large_for_a_clear_regression() {
+        let new = hyperfine_result(1.3, 0.05, 20);
+        let base = hyperfine_result(1.0, 0.05, 20);
+
+        let (t, df) = welch_t_test(&new, &base);
+
+        assert!(t > 0.0, "t should be positive when the new mean is higher: {}", t);
+        assert!(t > 10.0, "t should be large for a 30% shift at this variance: {}", t);
+        // equal variances and equal n collapse the Welch-Satterthwaite df close to
+        // the pooled n1 + n2 - 2 = 38, which is a useful sign-error tripwire.
+        assert!((df - 38.0).abs() < 1.0, "df should be close to 38: {}", df);
+    }
+
+    #[test]
+    fn welch_t_test_is_negative_for_an_improvement() {
+        let new = hyperfine_result(0.8, 0.05, 20);
+        let base = hyperfine_result(1.0, 0.05, 20);
+
+        let (t, _) = welch_t_test(&new, &base);
+
+        assert!(t < 0.0, "t should be negative when the new mean is lower: {}", t);
+    }
+
+    #[test]
+    fn compare_flags_a_known_regression() {
+        let s = sample(hyperfine_result(1.3, 0.05, 20));
+        let m = model(hyperfine_result(1.0, 0.05, 20));
+
+        let (verdict, stats) =
+            compare(&s, &m, DEFAULT_SIGNIFICANCE_LEVEL, DEFAULT_MIN_RELATIVE_SLOWDOWN);
+
+        assert_eq!(verdict, Verdict::Regressed);
+        assert!(stats.relative_change > 0.05);
+    }
+
+    #[test]
+    fn compare_reports_no_change_for_a_tiny_shift_below_the_slowdown_floor() {
+        // a 3% shift clears statistical significance at this variance (t ~ 1.9),
+        // but should still be gated out by the 5% minimum relative slowdown.
+        let s = sample(hyperfine_result(1.03, 0.05, 20));
+        let m = model(hyperfine_result(1.0, 0.05, 20));
+
+        let (verdict, _) = compare(&s, &m, DEFAULT_SIGNIFICANCE_LEVEL, DEFAULT_MIN_RELATIVE_SLOWDOWN);
+
+        assert_eq!(verdict, Verdict::NoChange);
+    }
+
+    #[test]
+    fn compare_flags_an_improvement() {
+        let s = sample(hyperfine_result(0.7, 0.05, 20));
+        let m = model(hyperfine_result(1.0, 0.05, 20));
+
+        let (verdict, _) = compare(&s, &m, DEFAULT_SIGNIFICANCE_LEVEL, DEFAULT_MIN_RELATIVE_SLOWDOWN);
+
+        assert_eq!(verdict, Verdict::Improved);
+    }
+
+    #[test]
+    fn compare_falls_back_to_a_sigma_threshold_with_too_few_runs() {
+        let s = sample(hyperfine_result(2.0, 0.1, 1));
+        let m = model(hyperfine_result(1.0, 0.1, 1));
+
+        let (verdict, stats) = compare(&s, &m, DEFAULT_SIGNIFICANCE_LEVEL, DEFAULT_MIN_RELATIVE_SLOWDOWN);
+
+        assert_eq!(verdict, Verdict::Regressed);
+        assert!(stats.t_statistic.is_none());
+    }
+
+    #[test]
+    fn memory_regressed_flags_a_large_rss_increase() {
+        let s = sample(hyperfine_result_with_rss(1.0, 0.05, 20, 200_000));
+        let m = model(hyperfine_result_with_rss(1.0, 0.05, 20, 100_000));
+
+        assert!(memory_regressed(&s, &m, DEFAULT_MIN_RELATIVE_SLOWDOWN));
+    }
+
+    #[test]
+    fn memory_regressed_is_false_without_resource_data() {
+        let s = sample(hyperfine_result(1.0, 0.05, 20));
+        let m = model(hyperfine_result(1.0, 0.05, 20));
+
+        assert!(!memory_regressed(&s, &m, DEFAULT_MIN_RELATIVE_SLOWDOWN));
+    }
+
+    #[test]
+    fn is_comparable_flags_a_different_host() {
+        let mut other_host = provenance();
+        other_host.hostname = "laptop".to_owned();
+
+        assert!(is_comparable(&provenance(), &provenance()));
+        assert!(!is_comparable(&provenance(), &other_host));
+    }
+}
@@ -0,0 +1,73 @@
+use crate::exceptions::RunnerError;
+use crate::types::HyperfineCmd;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+// TOML has no bare top-level array, so `metrics.toml` nests the list under
+// a `metrics` key; `metrics.json` can just be the array directly.
+#[derive(Deserialize)]
+struct MetricsFile {
+    metrics: Vec<HyperfineCmd>,
+}
+
+// Loads the list of metrics to benchmark from a `metrics.toml` or `metrics.json`
+// file, keyed off the file extension. This replaces the old hardcoded `METRICS`
+// array: to add a new metric, add an entry to the config file instead of
+// recompiling the runner.
+pub fn load_metrics(path: &Path) -> Result<Vec<HyperfineCmd>, RunnerError> {
+    let contents = fs::read_to_string(path).or_else(|e| {
+        Err(RunnerError::InvalidMetricConfigErr(
+            path.to_path_buf(),
+            format!("could not read file: {}", e),
+        ))
+    })?;
+
+    let metrics: Vec<HyperfineCmd> = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => {
+            let file: MetricsFile = toml::from_str(&contents).or_else(|e| {
+                Err(RunnerError::InvalidMetricConfigErr(
+                    path.to_path_buf(),
+                    format!("could not parse toml: {}", e),
+                ))
+            })?;
+            file.metrics
+        }
+        Some("json") => serde_json::from_str(&contents).or_else(|e| {
+            Err(RunnerError::InvalidMetricConfigErr(
+                path.to_path_buf(),
+                format!("could not parse json: {}", e),
+            ))
+        })?,
+        other => {
+            return Err(RunnerError::InvalidMetricConfigErr(
+                path.to_path_buf(),
+                format!("unrecognized metrics config extension: {:?}", other),
+            ))
+        }
+    };
+
+    validate_metrics(path, metrics)
+}
+
+// Checks that the loaded metric list is usable before anything runs hyperfine with it:
+// non-empty, and every entry has the fields a benchmark run actually needs.
+fn validate_metrics(path: &Path, metrics: Vec<HyperfineCmd>) -> Result<Vec<HyperfineCmd>, RunnerError> {
+    if metrics.is_empty() {
+        return Err(RunnerError::InvalidMetricConfigErr(
+            path.to_path_buf(),
+            "metrics list is empty".to_owned(),
+        ));
+    }
+
+    for metric in &metrics {
+        if metric.name.is_empty() || metric.cmd.is_empty() {
+            return Err(RunnerError::InvalidMetricConfigErr(
+                path.to_path_buf(),
+                format!("metric {:?} is missing a name or cmd", metric),
+            ));
+        }
+    }
+
+    Ok(metrics)
+}
@@ -0,0 +1,107 @@
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+// Errors that originate from filesystem / process IO, wrapped with enough
+// context (the path or command involved) to produce a useful message.
+#[derive(Debug)]
+pub enum IOError {
+    ReadErr(PathBuf, Option<std::io::Error>),
+    WriteErr(PathBuf, Option<std::io::Error>),
+    BadFileContentsErr(PathBuf, Option<std::io::Error>),
+    MissingFilenameErr(PathBuf),
+    FilenameNotUnicodeErr(PathBuf),
+    CannotRecreateTempDirErr(PathBuf, std::io::Error),
+    CommandErr(Option<std::io::Error>),
+}
+
+impl fmt::Display for IOError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IOError::ReadErr(path, source) => {
+                write!(f, "failed to read {:?}: {:?}", path, source)
+            }
+            IOError::WriteErr(path, source) => {
+                write!(f, "failed to write {:?}: {:?}", path, source)
+            }
+            IOError::BadFileContentsErr(path, source) => {
+                write!(f, "failed to read contents of {:?}: {:?}", path, source)
+            }
+            IOError::MissingFilenameErr(path) => write!(f, "{:?} has no filename", path),
+            IOError::FilenameNotUnicodeErr(path) => {
+                write!(f, "filename of {:?} is not valid unicode", path)
+            }
+            IOError::CannotRecreateTempDirErr(path, source) => {
+                write!(f, "could not recreate directory {:?}: {}", path, source)
+            }
+            IOError::CommandErr(source) => write!(f, "failed to run command: {:?}", source),
+        }
+    }
+}
+
+impl Error for IOError {}
+
+// Top level error type returned by everything this crate's binary calls into.
+#[derive(Debug)]
+pub enum RunnerError {
+    RunnerIOError(IOError),
+    BadJSONErr(PathBuf, Option<serde_json::Error>),
+    SerializationErr(serde_json::Error),
+    HyperfineUnexpectedExitCode(i32),
+    BaselineWithNoModelsErr(),
+    // the metric config file was missing required fields or had no entries.
+    InvalidMetricConfigErr(PathBuf, String),
+    // `compare` found at least one metric regressed against its baseline; carries
+    // the metric filenames that regressed so the CLI can report them and exit nonzero.
+    RegressionDetectedErr(Vec<String>),
+    // a CLI subcommand was invoked without a flag it requires, e.g. `model` without `--version`.
+    MissingFlagErr(String, String),
+    // a flag's value couldn't be parsed into the type the subcommand needed.
+    BadFlagValueErr(String, String),
+    // `compare`/`latest_baseline` found no baseline json files under the given directory
+    // (or its version subdirectories), so there's nothing to compare a Sample against.
+    NoBaselineFoundErr(PathBuf),
+}
+
+impl fmt::Display for RunnerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RunnerError::RunnerIOError(e) => write!(f, "{}", e),
+            RunnerError::BadJSONErr(path, source) => {
+                write!(f, "could not parse json in {:?}: {:?}", path, source)
+            }
+            RunnerError::SerializationErr(source) => {
+                write!(f, "could not serialize to json: {}", source)
+            }
+            RunnerError::HyperfineUnexpectedExitCode(code) => {
+                write!(f, "hyperfine exited with unexpected code {}", code)
+            }
+            RunnerError::BaselineWithNoModelsErr() => {
+                write!(f, "refusing to write a baseline with no models in it")
+            }
+            RunnerError::InvalidMetricConfigErr(path, reason) => {
+                write!(f, "invalid metric config at {:?}: {}", path, reason)
+            }
+            RunnerError::RegressionDetectedErr(metrics) => {
+                write!(f, "regression detected in: {}", metrics.join(", "))
+            }
+            RunnerError::MissingFlagErr(subcommand, flag) => {
+                write!(f, "{} requires {}", subcommand, flag)
+            }
+            RunnerError::BadFlagValueErr(flag, reason) => {
+                write!(f, "invalid value for {}: {}", flag, reason)
+            }
+            RunnerError::NoBaselineFoundErr(path) => {
+                write!(f, "no baseline json files found under {:?}", path)
+            }
+        }
+    }
+}
+
+impl Error for RunnerError {}
+
+impl From<IOError> for RunnerError {
+    fn from(e: IOError) -> Self {
+        RunnerError::RunnerIOError(e)
+    }
+}
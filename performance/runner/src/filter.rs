@@ -0,0 +1,71 @@
+// A project/metric name filter, e.g. `2000_models` or `parse`, used to restrict
+// `get_projects` to a single pair (or a handful) during local debugging instead
+// of running the whole matrix. `*` matches any run of characters; a bare name
+// with no `*` must match exactly.
+pub fn matches(pattern: &str, candidate: &str) -> bool {
+    if !pattern.contains('*') {
+        return pattern == candidate;
+    }
+
+    let ends_with_star = pattern.ends_with('*');
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let last = parts.len() - 1;
+    let mut rest = candidate;
+
+    for (i, part) in parts.iter().enumerate() {
+        if part.is_empty() {
+            continue;
+        }
+
+        // the final literal segment must reach all the way to the end of the
+        // candidate unless the pattern itself ends in `*` (i.e. "ends with" semantics).
+        if i == last && !ends_with_star {
+            return rest.ends_with(part);
+        }
+
+        match rest.find(part) {
+            Some(idx) if i == 0 && idx != 0 => return false,
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+
+    true
+}
+
+// a project-metric pair passes the filter if either name matches (or no filter was given).
+pub fn pair_matches(filter: Option<&str>, project_name: &str, metric_name: &str) -> bool {
+    match filter {
+        None => true,
+        Some(pattern) => matches(pattern, project_name) || matches(pattern, metric_name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_pattern_requires_exact_match() {
+        assert!(matches("parse", "parse"));
+        assert!(!matches("parse", "parse_incremental"));
+    }
+
+    #[test]
+    fn prefix_glob_matches_only_at_start() {
+        assert!(matches("2000_*", "2000_models"));
+        assert!(!matches("2000_*", "12000_models"));
+    }
+
+    #[test]
+    fn suffix_glob_is_anchored_to_the_end_of_the_candidate() {
+        assert!(matches("*_models", "2000_models"));
+        // a trailing suffix after the literal segment must not match.
+        assert!(!matches("*_models", "2000_models_extra"));
+    }
+
+    #[test]
+    fn bare_star_matches_anything() {
+        assert!(matches("*", "anything"));
+    }
+}
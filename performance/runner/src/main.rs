@@ -0,0 +1,152 @@
+mod compare;
+mod config;
+mod exceptions;
+mod filter;
+mod measure;
+mod provenance;
+mod resources;
+mod types;
+mod watch;
+
+use compare::Verdict;
+use exceptions::RunnerError;
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::str::FromStr;
+use std::time::Duration;
+use types::{Baseline, Version};
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let result = run(&args);
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), RunnerError> {
+    let metrics = config::load_metrics(&PathBuf::from("performance/metrics.toml"))?;
+    let name_filter = parse_filter_flag(args);
+
+    match args.get(1).map(|s| s.as_str()) {
+        Some("measure") => measure::take_samples(
+            &PathBuf::from("performance/projects"),
+            &PathBuf::from("performance/results"),
+            &metrics,
+            name_filter.as_deref(),
+        )
+        .map(|_| ()),
+        Some("watch") => watch::watch(
+            &PathBuf::from("performance/projects"),
+            &PathBuf::from("performance/results"),
+            &metrics,
+            args.iter().any(|a| a == "--recursive"),
+            &["target".to_owned(), "logs".to_owned()],
+            Duration::from_millis(500),
+            name_filter.as_deref(),
+        ),
+        Some("model") => {
+            let version = parse_flag(args, "--version")
+                .ok_or_else(|| RunnerError::MissingFlagErr("model".to_owned(), "--version".to_owned()))
+                .and_then(|v| {
+                    Version::from_str(&v).or_else(|e| Err(RunnerError::BadFlagValueErr("--version".to_owned(), e)))
+                })?;
+
+            measure::model(
+                version,
+                &PathBuf::from("performance/projects"),
+                &PathBuf::from("performance/baselines"),
+                &PathBuf::from("performance/tmp"),
+                10,
+                &metrics,
+                name_filter.as_deref(),
+            )
+            .map(|_| ())
+        }
+        Some("compare") => run_compare(&metrics, name_filter.as_deref()),
+        other => {
+            eprintln!("unknown or missing subcommand: {:?}", other);
+            process::exit(1);
+        }
+    }
+}
+
+// Takes a fresh Sample of each project-metric pair and compares it against the
+// matching MetricModel in the most recently written Baseline, printing a
+// verdict for each and returning an error (so the process exits nonzero) if
+// anything regressed.
+fn run_compare(metrics: &[types::HyperfineCmd], name_filter: Option<&str>) -> Result<(), RunnerError> {
+    let samples = measure::take_samples(
+        &PathBuf::from("performance/projects"),
+        &PathBuf::from("performance/results"),
+        metrics,
+        name_filter,
+    )?;
+
+    let baseline = latest_baseline(&PathBuf::from("performance/baselines"))?;
+
+    let mut regressed = Vec::new();
+
+    for sample in &samples {
+        let model = match baseline.models.iter().find(|m| m.metric == sample.metric) {
+            Some(model) => model,
+            None => continue, // no baseline for this metric yet; nothing to compare against.
+        };
+
+        if !compare::is_comparable(&sample.provenance, &baseline.provenance) {
+            println!(
+                "warning: {} was sampled on a different host/CPU than its baseline; verdict may not be meaningful",
+                sample.metric.filename()
+            );
+        }
+
+        let (verdict, stats) = compare::compare(
+            sample,
+            model,
+            compare::DEFAULT_SIGNIFICANCE_LEVEL,
+            compare::DEFAULT_MIN_RELATIVE_SLOWDOWN,
+        );
+        println!("{}: {:?} ({:?})", sample.metric.filename(), verdict, stats);
+
+        if compare::memory_regressed(sample, model, compare::DEFAULT_MIN_RELATIVE_SLOWDOWN) {
+            println!("{}: memory regressed", sample.metric.filename());
+            regressed.push(format!("{} (memory)", sample.metric.filename()));
+        }
+
+        if verdict == Verdict::Regressed {
+            regressed.push(sample.metric.filename());
+        }
+    }
+
+    if regressed.is_empty() {
+        Ok(())
+    } else {
+        Err(RunnerError::RegressionDetectedErr(regressed))
+    }
+}
+
+// Baselines live under a version subdirectory for each `model` run (see
+// `measure::model`), each containing the full baseline, so any one of them
+// has everything we need once we've found the most recent.
+fn latest_baseline(baselines_dir: &PathBuf) -> Result<Baseline, RunnerError> {
+    measure::read_baselines(baselines_dir)?
+        .into_iter()
+        .max_by_key(|baseline| baseline.provenance.end_ts)
+        .ok_or_else(|| RunnerError::NoBaselineFoundErr(baselines_dir.clone()))
+}
+
+// `--filter <pattern>`, used to restrict a run to a single project/metric pair.
+fn parse_filter_flag(args: &[String]) -> Option<String> {
+    parse_flag(args, "--filter")
+}
+
+fn parse_flag(args: &[String], flag: &str) -> Option<String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
@@ -1,7 +1,11 @@
 use crate::exceptions::{IOError, RunnerError};
+use crate::filter;
+use crate::provenance;
+use crate::resources::measure_resources;
 use crate::types::*;
 use chrono::prelude::*;
 use serde::de::DeserializeOwned;
+use std::collections::HashMap;
 use std::fs;
 use std::fs::DirEntry;
 use std::io;
@@ -9,13 +13,6 @@ use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus};
 use std::str::FromStr;
 
-// To add a new metric to the test suite, simply define it in this list
-static METRICS: [HyperfineCmd; 1] = [HyperfineCmd {
-    name: "parse",
-    prepare: "rm -rf target/",
-    cmd: "dbt parse --no-version-check",
-}];
-
 // TODO this could have it's impure parts split out and tested.
 //
 // Given a directory, read all files in the directory and return each
@@ -54,9 +51,40 @@ pub fn from_json_files<T: DeserializeOwned>(
         .collect()
 }
 
-fn get_projects<'a>(
+// Walks the version subdirectories under `baselines_dir` (the layout `model`
+// writes: `{baselines_dir}/{version}/{metric}.json`) and reads every Baseline
+// found in them. A flat `from_json_files` over `baselines_dir` itself would
+// always come back empty, since baselines never live directly in that directory.
+pub fn read_baselines(baselines_dir: &Path) -> Result<Vec<Baseline>, RunnerError> {
+    let entries = fs::read_dir(baselines_dir)
+        .or_else(|e| Err(IOError::ReadErr(baselines_dir.to_path_buf(), Some(e))))
+        .or_else(|e| Err(RunnerError::RunnerIOError(e)))?;
+
+    let mut baselines = Vec::new();
+
+    for entry in entries {
+        let path = entry
+            .or_else(|e| Err(IOError::ReadErr(baselines_dir.to_path_buf(), Some(e))))
+            .or_else(|e| Err(RunnerError::RunnerIOError(e)))?
+            .path();
+
+        if path.is_dir() {
+            baselines.extend(
+                from_json_files::<Baseline>(&path)?
+                    .into_iter()
+                    .map(|(_, baseline)| baseline),
+            );
+        }
+    }
+
+    Ok(baselines)
+}
+
+fn get_projects(
     projects_directory: &PathBuf,
-) -> Result<Vec<(PathBuf, String, HyperfineCmd<'a>)>, IOError> {
+    metrics: &[HyperfineCmd],
+    name_filter: Option<&str>,
+) -> Result<Vec<(PathBuf, String, HyperfineCmd)>, IOError> {
     let entries = fs::read_dir(projects_directory)
         .or_else(|e| Err(IOError::ReadErr(projects_directory.to_path_buf(), Some(e))))?;
 
@@ -75,15 +103,16 @@ fn get_projects<'a>(
                 })?
                 .to_owned();
 
-            // each project-metric pair we will run
-            let pairs = METRICS
+            // each project-metric pair we will run, restricted to ones matching the filter
+            let pairs = metrics
                 .iter()
+                .filter(|metric| filter::pair_matches(name_filter, &project_name, &metric.name))
                 .map(|metric| (path.clone(), project_name.clone(), metric.clone()))
-                .collect::<Vec<(PathBuf, String, HyperfineCmd<'a>)>>();
+                .collect::<Vec<(PathBuf, String, HyperfineCmd)>>();
 
             Ok(pairs)
         })
-        .collect::<Result<Vec<Vec<(PathBuf, String, HyperfineCmd<'a>)>>, IOError>>()?;
+        .collect::<Result<Vec<Vec<(PathBuf, String, HyperfineCmd)>>, IOError>>()?;
 
     Ok(unflattened_results.concat())
 }
@@ -93,10 +122,11 @@ fn run_hyperfine(
     command: &str,
     prep: &str,
     runs: i32,
+    expected_exit_code: Option<i32>,
     output_file: &PathBuf,
 ) -> Result<ExitStatus, IOError> {
-    Command::new("hyperfine")
-        .current_dir(run_dir)
+    let mut cmd = Command::new("hyperfine");
+    cmd.current_dir(run_dir)
         // warms filesystem caches by running the command first without counting it.
         // alternatively we could clear them before each run
         .arg("--warmup")
@@ -107,8 +137,17 @@ fn run_hyperfine(
         .arg("--max-runs")
         .arg(runs.to_string())
         .arg("--prepare")
-        .arg(prep)
-        .arg(command)
+        .arg(prep);
+
+    // for "error path" benchmarks, hyperfine itself treats a nonzero exit from
+    // the benchmarked command as fatal and aborts without writing
+    // --export-json, regardless of what we expect; --ignore-failure is what
+    // lets the exit-code check below actually run.
+    if expected_exit_code.unwrap_or(0) != 0 {
+        cmd.arg("--ignore-failure");
+    }
+
+    cmd.arg(command)
         .arg("--export-json")
         .arg(output_file)
         // this prevents hyperfine from capturing dbt's output.
@@ -128,14 +167,23 @@ fn clear_dir(dir: &PathBuf) -> Result<(), io::Error> {
 
 // deletes the output directory, makes one hyperfine run for each project-metric pair,
 // reads in the results, and returns a Sample for each project-metric pair.
-pub fn take_samples(projects_dir: &PathBuf, out_dir: &PathBuf) -> Result<Vec<Sample>, RunnerError> {
+pub fn take_samples(
+    projects_dir: &PathBuf,
+    out_dir: &PathBuf,
+    metrics: &[HyperfineCmd],
+    name_filter: Option<&str>,
+) -> Result<Vec<Sample>, RunnerError> {
     clear_dir(out_dir).or_else(|e| Err(IOError::CannotRecreateTempDirErr(out_dir.clone(), e)))?;
 
     // using one time stamp for all samples.
     let ts = Utc::now();
 
+    // resource usage isn't part of hyperfine's json, so we track it ourselves,
+    // keyed by the same filename used for the hyperfine output.
+    let mut resources: HashMap<String, ResourceUsage> = HashMap::new();
+
     // run hyperfine in serial for each project-metric pair
-    for (path, project_name, hcmd) in get_projects(projects_dir)? {
+    for (path, project_name, hcmd) in get_projects(projects_dir, metrics, name_filter)? {
         let metric = Metric {
             name: hcmd.name.to_owned(),
             project_name: project_name.to_owned(),
@@ -145,15 +193,25 @@ pub fn take_samples(projects_dir: &PathBuf, out_dir: &PathBuf) -> Result<Vec<Sam
         let mut output_file = out_dir.clone();
         output_file.push(metric.filename());
 
-        let status = run_hyperfine(&path, &command, hcmd.clone().prepare, 1, &output_file)
+        let status = run_hyperfine(&path, &command, &hcmd.prepare, 1, hcmd.expected_exit_code, &output_file)
             .or_else(|e| Err(RunnerError::from(e)))?;
 
+        let expected_code = hcmd.expected_exit_code.unwrap_or(0);
         match status.code() {
-            Some(code) if code != 0 => return Err(RunnerError::HyperfineNonZeroExitCode(code)),
+            Some(code) if code != expected_code => {
+                return Err(RunnerError::HyperfineUnexpectedExitCode(code))
+            }
             _ => (),
         }
+
+        if let Ok(usage) = measure_resources(&path, &command, &hcmd.prepare) {
+            resources.insert(metric.filename(), usage);
+        }
     }
 
+    // captured once at the end of the run, and attached to every sample.
+    let provenance = provenance::capture();
+
     let samples = from_json_files::<Measurements>(out_dir)?
         .into_iter()
         .map(|(path, measurement)| {
@@ -161,11 +219,11 @@ pub fn take_samples(projects_dir: &PathBuf, out_dir: &PathBuf) -> Result<Vec<Sam
             // `file_name` is boop___proj.json. `file_stem` is boop___proj.
             let filename = path.file_stem().unwrap();
             let metric = Metric::from_str(&filename.to_string_lossy().into_owned()).unwrap();
-            Sample::from_measurement(
-                metric,
-                ts,
-                &measurement.results[0], // TODO do it safer
-            )
+
+            let mut result = measurement.results[0].clone(); // TODO do it safer
+            result.resources = resources.get(&metric.filename()).copied();
+
+            Sample::from_measurement(metric, ts, &result, provenance.clone())
         })
         .collect();
 
@@ -174,37 +232,56 @@ pub fn take_samples(projects_dir: &PathBuf, out_dir: &PathBuf) -> Result<Vec<Sam
 
 // Calls hyperfine via system command, reads in the results, and writes out Baseline json files.
 // Intended to be called after each new version is released.
-pub fn model<'a>(
+pub fn model(
     version: Version,
     projects_directory: &PathBuf,
     out_dir: &PathBuf,
     tmp_dir: &PathBuf,
     n_runs: i32,
+    metrics: &[HyperfineCmd],
+    name_filter: Option<&str>,
 ) -> Result<Baseline, RunnerError> {
-    for (path, project_name, hcmd) in get_projects(projects_directory)? {
+    let mut resources: HashMap<String, ResourceUsage> = HashMap::new();
+
+    for (path, project_name, hcmd) in get_projects(projects_directory, metrics, name_filter)? {
         let metric = Metric {
             name: hcmd.name.to_owned(),
             project_name: project_name.to_owned(),
         };
 
-        let command = format!("{} --profiles-dir ../../project_config/", hcmd.clone().cmd);
+        let command = format!("{} --profiles-dir ../../project_config/", hcmd.cmd);
         let mut tmp_file = tmp_dir.clone();
         tmp_file.push(metric.filename());
 
-        let status = run_hyperfine(&path, &command, hcmd.clone().prepare, n_runs, &tmp_file)
+        // an individual metric can override how many hyperfine runs it gets.
+        let runs = hcmd.runs.unwrap_or(n_runs);
+        let status = run_hyperfine(&path, &command, &hcmd.prepare, runs, hcmd.expected_exit_code, &tmp_file)
             .or_else(|e| Err(RunnerError::from(e)))?;
 
+        let expected_code = hcmd.expected_exit_code.unwrap_or(0);
         match status.code() {
-            Some(code) if code != 0 => return Err(RunnerError::HyperfineNonZeroExitCode(code)),
+            Some(code) if code != expected_code => {
+                return Err(RunnerError::HyperfineUnexpectedExitCode(code))
+            }
             _ => (),
         }
+
+        if let Ok(usage) = measure_resources(&path, &command, &hcmd.prepare) {
+            resources.insert(metric.filename(), usage);
+        }
     }
 
     // read what hyperfine wrote
     let measurements: Vec<(PathBuf, Measurements)> = from_json_files::<Measurements>(tmp_dir)?;
 
     // put it in the right format using the same timestamp for every model.
-    let baseline = from_measurements(version, &measurements, Some(Utc::now()))?;
+    let baseline = from_measurements(
+        version,
+        &measurements,
+        Some(Utc::now()),
+        &resources,
+        provenance::capture(),
+    )?;
 
     // write a file for each baseline measurement
     for model in &baseline.models {
@@ -238,6 +315,8 @@ fn from_measurements(
     version: Version,
     measurements: &[(PathBuf, Measurements)],
     ts: Option<DateTime<Utc>>,
+    resources: &HashMap<String, ResourceUsage>,
+    provenance: Provenance,
 ) -> Result<Baseline, RunnerError> {
     let models: Vec<MetricModel> = measurements
         .into_iter()
@@ -246,11 +325,15 @@ fn from_measurements(
             // `file_name` is boop___proj.json. `file_stem` is boop___proj.
             let filename = path.file_stem().unwrap();
             let metric = Metric::from_str(&filename.to_string_lossy()).unwrap();
+
+            let mut measurement = measurements.results[0].clone();
+            measurement.resources = resources.get(&metric.filename()).copied();
+
             MetricModel {
                 metric: metric,
                 // uses the provided timestamp for every entry, or the current time if None.
                 ts: ts.unwrap_or(Utc::now()),
-                measurement: measurements.results[0].clone(),
+                measurement,
             }
         })
         .collect();
@@ -261,6 +344,90 @@ fn from_measurements(
         Ok(Baseline {
             version: version,
             models: models,
+            provenance,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // unique-per-test scratch directory under the OS temp dir, since this
+    // module's tests exercise real directory layouts rather than in-memory
+    // data.
+    fn temp_dir(name: &str) -> PathBuf {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("runner-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn provenance_at(end_ts: DateTime<Utc>) -> Provenance {
+        Provenance {
+            dbt_commit_sha: Some("deadbeef".to_owned()),
+            hostname: "ci-runner".to_owned(),
+            os: "linux".to_owned(),
+            arch: "x86_64".to_owned(),
+            cpu_model: "generic".to_owned(),
+            cpu_cores: 4,
+            end_ts,
+        }
+    }
+
+    // writes a baseline json file at the exact path `model` would have
+    // written it: `{baselines_dir}/{version}/{metric}.json`.
+    fn write_baseline(baselines_dir: &Path, version: &str, end_ts: DateTime<Utc>) {
+        let version_dir = baselines_dir.join(version);
+        fs::create_dir_all(&version_dir).unwrap();
+
+        let baseline = Baseline {
+            version: Version::from_str(version).unwrap(),
+            models: vec![],
+            provenance: provenance_at(end_ts),
+        };
+
+        let mut out_file = version_dir;
+        out_file.push("parse___2000_models.json");
+        fs::write(out_file, serde_json::to_string(&baseline).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn read_baselines_walks_the_version_subdirectories_model_writes() {
+        let dir = temp_dir("read-baselines");
+        write_baseline(&dir, "1.0.0", Utc.timestamp(1, 0));
+        write_baseline(&dir, "1.1.0", Utc.timestamp(2, 0));
+
+        let baselines = read_baselines(&dir).unwrap();
+
+        assert_eq!(baselines.len(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn read_baselines_is_empty_for_a_directory_with_no_version_subdirectories() {
+        let dir = temp_dir("read-baselines-empty");
+
+        let baselines = read_baselines(&dir).unwrap();
+
+        assert!(baselines.is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    // end-to-end: without `--ignore-failure`, hyperfine aborts on the
+    // benchmarked command's nonzero exit and never writes --export-json, so
+    // this would fail before our own exit-code check ever runs.
+    #[test]
+    fn run_hyperfine_does_not_abort_on_an_expected_nonzero_exit() {
+        let dir = temp_dir("run-hyperfine-ignore-failure");
+        let mut output_file = dir.clone();
+        output_file.push("exit___nonzero.json");
+
+        let status = run_hyperfine(&dir, "exit 3", "true", 1, Some(3), &output_file).unwrap();
+
+        assert!(status.success());
+        assert!(output_file.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::types::Provenance;
+use chrono::Utc;
+use std::fs;
+use std::process::Command;
+
+// Captures environment/provenance info at the end of a `take_samples`/`model`
+// run: the dbt commit under test, and the machine it ran on. This lets the
+// comparison subsystem warn when a Sample and Baseline aren't comparable
+// because they came from different hosts.
+pub fn capture() -> Provenance {
+    Provenance {
+        dbt_commit_sha: git_commit_sha(),
+        hostname: hostname(),
+        os: std::env::consts::OS.to_owned(),
+        arch: std::env::consts::ARCH.to_owned(),
+        cpu_model: cpu_model(),
+        cpu_cores: num_cpus::get(),
+        end_ts: Utc::now(),
+    }
+}
+
+fn git_commit_sha() -> Option<String> {
+    Command::new("git")
+        .arg("rev-parse")
+        .arg("HEAD")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+fn hostname() -> String {
+    Command::new("hostname")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+// on Linux, pulls the `model name` field out of /proc/cpuinfo; falls back to
+// "unknown" on platforms (or sandboxes) where that isn't available.
+fn cpu_model() -> String {
+    fs::read_to_string("/proc/cpuinfo")
+        .ok()
+        .and_then(|contents| {
+            contents
+                .lines()
+                .find(|line| line.starts_with("model name"))
+                .and_then(|line| line.split(':').nth(1))
+                .map(|s| s.trim().to_owned())
+        })
+        .unwrap_or_else(|| "unknown".to_owned())
+}
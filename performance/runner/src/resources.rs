@@ -0,0 +1,60 @@
+use crate::exceptions::IOError;
+use crate::types::ResourceUsage;
+use std::path::PathBuf;
+use std::process::Command;
+
+// Runs `prep` then `command` once more under `/usr/bin/time -v`, to capture peak
+// RSS and CPU time alongside the timing hyperfine already gives us. This is a
+// single extra run rather than folding it into hyperfine's own repetitions,
+// since hyperfine doesn't expose per-run rusage.
+pub fn measure_resources(run_dir: &PathBuf, command: &str, prep: &str) -> Result<ResourceUsage, IOError> {
+    Command::new("sh")
+        .current_dir(run_dir)
+        .arg("-c")
+        .arg(prep)
+        .status()
+        .or_else(|e| Err(IOError::CommandErr(Some(e))))?;
+
+    let output = Command::new("/usr/bin/time")
+        .current_dir(run_dir)
+        .arg("-v")
+        .arg("sh")
+        .arg("-c")
+        .arg(command)
+        .output()
+        .or_else(|e| Err(IOError::CommandErr(Some(e))))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    parse_time_v_output(&stderr)
+}
+
+// Parses the lines `/usr/bin/time -v` writes to stderr, e.g.:
+//   Maximum resident set size (kbytes): 123456
+//   User time (seconds): 1.23
+//   System time (seconds): 0.45
+fn parse_time_v_output(output: &str) -> Result<ResourceUsage, IOError> {
+    let mut max_rss_kb = None;
+    let mut user_cpu_s = None;
+    let mut system_cpu_s = None;
+
+    for line in output.lines() {
+        let line = line.trim();
+
+        if let Some(value) = line.strip_prefix("Maximum resident set size (kbytes):") {
+            max_rss_kb = value.trim().parse::<u64>().ok();
+        } else if let Some(value) = line.strip_prefix("User time (seconds):") {
+            user_cpu_s = value.trim().parse::<f64>().ok();
+        } else if let Some(value) = line.strip_prefix("System time (seconds):") {
+            system_cpu_s = value.trim().parse::<f64>().ok();
+        }
+    }
+
+    match (max_rss_kb, user_cpu_s, system_cpu_s) {
+        (Some(max_rss_kb), Some(user_cpu_s), Some(system_cpu_s)) => Ok(ResourceUsage {
+            max_rss_kb,
+            user_cpu_s,
+            system_cpu_s,
+        }),
+        _ => Err(IOError::CommandErr(None)),
+    }
+}
@@ -0,0 +1,171 @@
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+// A single dbt invocation to benchmark: what to run, and how to reset
+// the project directory before each run. Loaded from `metrics.toml`/`metrics.json`
+// by `config::load_metrics`, so fields are owned rather than borrowed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HyperfineCmd {
+    pub name: String,
+    pub prepare: String,
+    pub cmd: String,
+    // overrides the number of hyperfine runs for `model`, if given.
+    #[serde(default)]
+    pub runs: Option<i32>,
+    // for "error path" benchmarks where dbt is supposed to fail: the exit code
+    // hyperfine should treat as success. Defaults to 0 if not given.
+    #[serde(default)]
+    pub expected_exit_code: Option<i32>,
+}
+
+// a project-metric pair, identifying one benchmark result.
+// serialized into filenames as "{name}___{project_name}".
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metric {
+    pub name: String,
+    pub project_name: String,
+}
+
+impl Metric {
+    pub fn filename(&self) -> String {
+        format!("{}___{}", self.name, self.project_name)
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.splitn(2, "___").collect::<Vec<&str>>().as_slice() {
+            [name, project_name] => Ok(Metric {
+                name: (*name).to_owned(),
+                project_name: (*project_name).to_owned(),
+            }),
+            _ => Err(format!("could not parse metric from filename {}", s)),
+        }
+    }
+}
+
+// a semver-ish dbt version, used to name baseline directories.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.splitn(3, '.').collect::<Vec<&str>>().as_slice() {
+            [major, minor, patch] => {
+                let parse = |part: &str| {
+                    part.parse::<u32>()
+                        .or_else(|_| Err(format!("could not parse version from {:?}", s)))
+                };
+                Ok(Version {
+                    major: parse(major)?,
+                    minor: parse(minor)?,
+                    patch: parse(patch)?,
+                })
+            }
+            _ => Err(format!("could not parse version from {:?}", s)),
+        }
+    }
+}
+
+// one entry of hyperfine's --export-json output, plus the resource usage we
+// capture ourselves since hyperfine only measures wall-clock time.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HyperfineResult {
+    pub command: String,
+    pub mean: f64,
+    pub stddev: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+    pub times: Vec<f64>,
+    #[serde(default)]
+    pub resources: Option<ResourceUsage>,
+}
+
+// Peak memory and CPU time for a single benchmarked command, captured by
+// wrapping the command in `/usr/bin/time -v` (see `resources::measure_resources`).
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    pub max_rss_kb: u64,
+    pub user_cpu_s: f64,
+    pub system_cpu_s: f64,
+}
+
+// the top-level shape of a hyperfine --export-json file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Measurements {
+    pub results: Vec<HyperfineResult>,
+}
+
+// Where a Sample or Baseline came from, captured once per `take_samples`/`model`
+// run so historical JSON files can be attributed, and so results taken on
+// different machines can be flagged as not comparable.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Provenance {
+    pub dbt_commit_sha: Option<String>,
+    pub hostname: String,
+    pub os: String,
+    pub arch: String,
+    pub cpu_model: String,
+    pub cpu_cores: usize,
+    pub end_ts: DateTime<Utc>,
+}
+
+// a single observation taken during `take_samples`, to be compared against
+// a Baseline's MetricModel for the same Metric.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Sample {
+    pub metric: Metric,
+    pub ts: DateTime<Utc>,
+    pub measurement: HyperfineResult,
+    pub provenance: Provenance,
+}
+
+impl Sample {
+    pub fn from_measurement(
+        metric: Metric,
+        ts: DateTime<Utc>,
+        measurement: &HyperfineResult,
+        provenance: Provenance,
+    ) -> Sample {
+        Sample {
+            metric,
+            ts,
+            measurement: measurement.clone(),
+            provenance,
+        }
+    }
+}
+
+// a modeled, trusted measurement for one Metric, persisted as part of a Baseline.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MetricModel {
+    pub metric: Metric,
+    pub ts: DateTime<Utc>,
+    pub measurement: HyperfineResult,
+}
+
+// everything measured for a single dbt version, written out as
+// `{out_dir}/{version}/{metric.filename()}.json`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: Version,
+    pub models: Vec<MetricModel>,
+    pub provenance: Provenance,
+}
@@ -0,0 +1,154 @@
+use crate::exceptions::{IOError, RunnerError};
+use crate::filter;
+use crate::measure::take_samples;
+use crate::types::HyperfineCmd;
+use notify::{watcher, DebouncedEvent, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+// Re-runs `take_samples` every time a change is observed under `projects_dir`,
+// so contributors get a perf signal while editing without re-invoking the runner
+// by hand. Debounces bursts of filesystem events (e.g. a whole `target/` directory
+// being recreated) into a single run, and skips paths matching `ignore_globs` so
+// a metric's own `prepare` step (e.g. `rm -rf target/`) doesn't re-trigger itself.
+pub fn watch(
+    projects_dir: &PathBuf,
+    out_dir: &PathBuf,
+    metrics: &[HyperfineCmd],
+    recursive: bool,
+    ignore_globs: &[String],
+    debounce: Duration,
+    name_filter: Option<&str>,
+) -> Result<(), RunnerError> {
+    let (tx, rx) = channel();
+
+    let mut watcher = watcher(tx, debounce)
+        .or_else(|_| Err(RunnerError::RunnerIOError(IOError::CommandErr(None))))?;
+
+    let mode = if recursive {
+        RecursiveMode::Recursive
+    } else {
+        // non-recursive: watch only the top-level project directories, so churn
+        // inside e.g. `target/` (which a metric's own `prepare` step recreates)
+        // doesn't re-trigger a run.
+        RecursiveMode::NonRecursive
+    };
+
+    if recursive {
+        watcher
+            .watch(projects_dir, mode)
+            .or_else(|_| Err(RunnerError::RunnerIOError(IOError::ReadErr(projects_dir.clone(), None))))?;
+    } else {
+        for entry in std::fs::read_dir(projects_dir)
+            .or_else(|e| Err(IOError::ReadErr(projects_dir.clone(), Some(e))))?
+        {
+            let path = entry
+                .or_else(|e| Err(IOError::ReadErr(projects_dir.clone(), Some(e))))?
+                .path();
+
+            if path.is_dir() {
+                watcher
+                    .watch(&path, mode)
+                    .or_else(|_| Err(RunnerError::RunnerIOError(IOError::ReadErr(path.clone(), None))))?;
+            }
+        }
+    }
+
+    println!("watching {:?} for changes...", projects_dir);
+
+    loop {
+        // block for the first event, then drain any others notify has already
+        // coalesced/debounced so a burst only triggers one run below.
+        match rx.recv() {
+            Ok(event) => {
+                if should_trigger(&event, ignore_globs) {
+                    drain_pending(&rx);
+                    run_once(projects_dir, out_dir, metrics, name_filter);
+                }
+            }
+            Err(_) => return Ok(()), // watcher was dropped; nothing left to watch.
+        }
+    }
+}
+
+// consumes any already-queued events without blocking, since notify's debouncer
+// can still emit more than one event for a single logical change.
+fn drain_pending(rx: &std::sync::mpsc::Receiver<DebouncedEvent>) {
+    while rx.try_recv().is_ok() {}
+}
+
+fn should_trigger(event: &DebouncedEvent, ignore_globs: &[String]) -> bool {
+    let path = match event {
+        DebouncedEvent::Create(p)
+        | DebouncedEvent::Write(p)
+        | DebouncedEvent::Remove(p)
+        | DebouncedEvent::Rename(p, _) => p.clone(),
+        _ => return false,
+    };
+
+    !is_ignored(&path, ignore_globs)
+}
+
+// Matches each path *component* against the ignore globs, rather than the
+// whole path as one string: `notify` hands us bare directory paths like
+// `.../2000_models/target` with no trailing slash, so a pattern like
+// `target/` would never match via substring containment. Trimming the
+// trailing slash and comparing component-by-component (reusing the same
+// glob semantics `filter::matches` uses for project/metric names) catches
+// that path regardless of where `target` sits in it.
+fn is_ignored(path: &Path, ignore_globs: &[String]) -> bool {
+    ignore_globs.iter().any(|pattern| {
+        let pattern = pattern.trim_end_matches('/');
+        path.components()
+            .any(|component| filter::matches(pattern, &component.as_os_str().to_string_lossy()))
+    })
+}
+
+fn run_once(projects_dir: &PathBuf, out_dir: &PathBuf, metrics: &[HyperfineCmd], name_filter: Option<&str>) {
+    println!("change detected, re-running benchmarks...");
+
+    match take_samples(projects_dir, out_dir, metrics, name_filter) {
+        Ok(samples) => println!("took {} sample(s)", samples.len()),
+        Err(e) => eprintln!("benchmark run failed: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_ignores() -> Vec<String> {
+        vec!["target/".to_owned(), "logs/".to_owned()]
+    }
+
+    #[test]
+    fn ignores_a_bare_target_directory_path_like_notify_emits() {
+        // this is the exact shape `notify` hands us: no trailing slash, and
+        // `target` can be nested arbitrarily deep under the project root.
+        let path = PathBuf::from("performance/projects/2000_models/target");
+        assert!(is_ignored(&path, &default_ignores()));
+    }
+
+    #[test]
+    fn does_not_ignore_unrelated_paths() {
+        let path = PathBuf::from("performance/projects/2000_models/models/my_model.sql");
+        assert!(!is_ignored(&path, &default_ignores()));
+    }
+
+    #[test]
+    fn should_trigger_is_false_for_the_metrics_own_prepare_step_output() {
+        let event = DebouncedEvent::Create(PathBuf::from(
+            "performance/projects/2000_models/target/manifest.json",
+        ));
+        assert!(!should_trigger(&event, &default_ignores()));
+    }
+
+    #[test]
+    fn should_trigger_is_true_for_real_source_changes() {
+        let event = DebouncedEvent::Write(PathBuf::from(
+            "performance/projects/2000_models/models/my_model.sql",
+        ));
+        assert!(should_trigger(&event, &default_ignores()));
+    }
+}